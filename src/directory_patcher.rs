@@ -0,0 +1,248 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use ignore::overrides::OverrideBuilder;
+use ignore::types::{Types, TypesBuilder};
+use ignore::WalkBuilder;
+
+use crate::file_patcher::FilePatcher;
+use crate::query::Query;
+use crate::settings::Settings;
+use crate::stats::Stats;
+
+/// Walks a directory tree and patches every file it contains, according to `Settings`.
+pub struct DirectoryPatcher<'a> {
+    path: PathBuf,
+    settings: &'a Settings,
+    stats: Stats,
+}
+
+impl<'a> DirectoryPatcher<'a> {
+    pub fn new(path: &Path, settings: &'a Settings) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            settings,
+            stats: Stats::default(),
+        }
+    }
+
+    pub fn stats(self) -> Stats {
+        self.stats
+    }
+
+    pub fn run(&mut self, query: &Query) -> Result<()> {
+        if let Some(files_from) = self.settings.files_from.clone() {
+            self.run_files_from(&files_from, query)?;
+            return self.emit_output();
+        }
+
+        let selected = build_selected_globset(&self.settings.selected_file_types)?;
+        let types = build_types(&self.settings.ignored_file_types)?;
+
+        if self.settings.include_patterns.is_empty() {
+            let root = self.path.clone();
+            self.run_root(&root, None, &selected, &types, query)?;
+        } else {
+            for pattern in self.settings.include_patterns.clone() {
+                let (base_dir, glob_pattern) = split_include_pattern(&self.path, &pattern);
+                self.run_root(&base_dir, Some(&glob_pattern), &selected, &types, query)?;
+            }
+        }
+
+        self.emit_output()
+    }
+
+    /// Patch exactly the files listed in `files_from`, one path per line, bypassing
+    /// directory traversal entirely. Relative entries are resolved against the run root.
+    fn run_files_from(&mut self, files_from: &Path, query: &Query) -> Result<()> {
+        let contents = std::fs::read_to_string(files_from)
+            .with_context(|| format!("could not read {:?}", files_from))?;
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let entry_path = Path::new(line);
+            let entry_path = if entry_path.is_absolute() {
+                entry_path.to_path_buf()
+            } else {
+                self.path.join(entry_path)
+            };
+            self.patch_file(&entry_path, query)?;
+        }
+        Ok(())
+    }
+
+    fn emit_output(&self) -> Result<()> {
+        match self.settings.output_format {
+            crate::OutputFormat::Text => {}
+            crate::OutputFormat::UnifiedDiff => {
+                for file_result in self.stats.file_results() {
+                    crate::output::print_unified_diff(file_result);
+                }
+            }
+            crate::OutputFormat::Json => {
+                crate::output::print_json(self.stats.file_results())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk `root`, optionally restricted to `include_glob`, patching every matching file.
+    ///
+    /// `include_glob` is handed to the `ignore` walker as a whitelist override, so files
+    /// that do not match it are skipped by the walker itself. Directories are not filtered
+    /// by it: every directory below `root` is still descended into.
+    fn run_root(
+        &mut self,
+        root: &Path,
+        include_glob: Option<&str>,
+        selected: &Option<globset::GlobSet>,
+        types: &Types,
+        query: &Query,
+    ) -> Result<()> {
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .hidden(!self.settings.hidden)
+            .ignore(!self.settings.ignored)
+            .git_ignore(!self.settings.ignored)
+            // `ruplacer` runs on arbitrary directories, not just git working trees, so
+            // `.gitignore` files should still be honored outside of one.
+            .require_git(false)
+            .types(types.clone());
+        for filename in &self.settings.custom_ignore_filenames {
+            builder.add_custom_ignore_filename(filename);
+        }
+        if let Some(glob_pattern) = include_glob {
+            let mut overrides = OverrideBuilder::new(root);
+            overrides.add(glob_pattern)?;
+            builder.overrides(overrides.build()?);
+        }
+
+        for entry in builder.build() {
+            let entry = entry?;
+            if entry.file_type().is_none_or(|t| !t.is_file()) {
+                continue;
+            }
+            let entry_path = entry.path();
+            // Matched lazily against this single entry: no upfront glob expansion.
+            if let Some(selected) = selected {
+                let file_name = entry_path.file_name().unwrap_or_default();
+                if !selected.is_match(file_name) {
+                    continue;
+                }
+            }
+            self.patch_file(entry_path, query)?;
+        }
+        Ok(())
+    }
+
+    fn patch_file(&mut self, path: &Path, query: &Query) -> Result<()> {
+        let file_patcher = FilePatcher::new(path, query, self.settings)?;
+        let file_patcher = match file_patcher {
+            Some(file_patcher) => file_patcher,
+            None => return Ok(()),
+        };
+        if !self.settings.dry_run {
+            file_patcher.write()?;
+        }
+        self.stats.on_file_patched(file_patcher.num_replacements());
+        if self.settings.output_format != crate::OutputFormat::Text {
+            let (before_text, after_text) = match file_patcher.diff_text() {
+                Some((before, after)) => (Some(before.to_string()), Some(after.to_string())),
+                None => (None, None),
+            };
+            self.stats.add_file_result(crate::FileResult {
+                path: file_patcher.path().to_path_buf(),
+                replacements: file_patcher.replacements().to_vec(),
+                before_text,
+                after_text,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Compile `selected_file_types` into a single `GlobSet` matched against each entry's file
+/// name while walking, instead of eagerly expanding every pattern across the whole tree.
+///
+/// Returns `None` when no selection was requested, meaning every file is allowed.
+fn build_selected_globset(patterns: &[String]) -> Result<Option<globset::GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob_pattern = to_glob_pattern(pattern);
+        builder.add(globset::Glob::new(&glob_pattern)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+fn to_glob_pattern(pattern: &str) -> String {
+    if is_glob_pattern(pattern) {
+        pattern.to_string()
+    } else {
+        format!("*.{}", pattern)
+    }
+}
+
+/// Build the `ignore::types::Types` used to skip `ignored_file_types` while walking.
+fn build_types(patterns: &[String]) -> Result<Types> {
+    let mut builder = TypesBuilder::new();
+    builder.add_defaults();
+    for (i, pattern) in patterns.iter().enumerate() {
+        if is_glob_pattern(pattern) {
+            // Validate upfront: `TypesBuilder::add` only stores the glob, it doesn't
+            // parse it until `build()`, and we want a consistent error message
+            // regardless of whether the pattern is malformed or simply unknown.
+            globset::Glob::new(pattern)
+                .map_err(|_| anyhow!("unrecognized file type: {}", pattern))?;
+            let name = format!("ruplacerignored{}", i);
+            builder.add(&name, pattern)?;
+            builder.negate(&name);
+        } else {
+            builder.negate(pattern);
+        }
+    }
+    builder.build().map_err(|err| anyhow!("{}", err))
+}
+
+fn is_glob_pattern(pattern: &str) -> bool {
+    pattern.chars().any(|c| matches!(c, '*' | '?' | '[' | ']'))
+}
+
+/// Split an explicit include path like `src/**/*.rs` into the literal directory prefix
+/// (`src`) and the remaining glob pattern (`**/*.rs`), so the walker can be rooted directly
+/// at the subtree that might contain matches instead of globbing from `root`.
+fn split_include_pattern(root: &Path, pattern: &str) -> (PathBuf, String) {
+    let mut base = PathBuf::new();
+    let mut rest: Vec<String> = Vec::new();
+    let mut in_glob = false;
+    for component in Path::new(pattern).components() {
+        let part = component.as_os_str().to_string_lossy().to_string();
+        if in_glob || is_glob_pattern(&part) {
+            in_glob = true;
+            rest.push(part);
+        } else {
+            base.push(&part);
+        }
+    }
+    let base_dir = if base.as_os_str().is_empty() {
+        root.to_path_buf()
+    } else if base.is_absolute() {
+        base
+    } else {
+        root.join(base)
+    };
+    let glob_pattern = if rest.is_empty() {
+        "**".to_string()
+    } else {
+        // Anchor to `base_dir`: override patterns follow gitignore syntax, where a
+        // pattern with no slash (e.g. `*.rs`) matches at any depth. Without the leading
+        // `/`, `src/*.rs` would also pull in `src/sub/nested.rs`; `**` stays opt-in.
+        format!("/{}", rest.join("/"))
+    };
+    (base_dir, glob_pattern)
+}
+
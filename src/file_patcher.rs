@@ -0,0 +1,225 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::query::Query;
+
+/// One replacement record for a file, suitable for reporting.
+///
+/// When patching text (UTF-8 or `Settings::encoding`) there is one record per changed
+/// line, with `before`/`after` holding the whole line, however many matches it had.
+/// In `Settings::binary` mode there is one record per match, with `before` holding
+/// the matched bytes.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Replacement {
+    pub line: usize,
+    pub column: usize,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug)]
+enum Contents {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+/// Computes (and, on request, applies) the replacements to perform on a single file.
+#[derive(Debug)]
+pub struct FilePatcher {
+    path: PathBuf,
+    original_text: Option<String>,
+    new_text: Option<String>,
+    new_contents: Contents,
+    replacements: Vec<Replacement>,
+}
+
+impl FilePatcher {
+    /// Try and build a `FilePatcher` for `path`.
+    ///
+    /// Returns `Ok(None)` when the file does not need patching, or when it cannot be
+    /// decoded and should be skipped rather than causing the whole run to fail.
+    pub fn new(path: &Path, query: &Query, settings: &crate::Settings) -> Result<Option<Self>> {
+        if settings.binary {
+            return Self::new_binary(path, query);
+        }
+        if let Some(label) = &settings.encoding {
+            return Self::new_encoded(path, query, label);
+        }
+        Self::new_text(path, query)
+    }
+
+    fn new_text(path: &Path, query: &Query) -> Result<Option<Self>> {
+        let input = match fs::read_to_string(path) {
+            Ok(input) => input,
+            // Not valid UTF-8: skip rather than fail the whole run.
+            Err(_) => return Ok(None),
+        };
+        let (new_contents, replacements) = patch_lines(&input, query);
+        if replacements.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            original_text: Some(input),
+            new_text: None,
+            new_contents: Contents::Text(new_contents),
+            replacements,
+        }))
+    }
+
+    fn new_binary(path: &Path, query: &Query) -> Result<Option<Self>> {
+        let input = fs::read(path).with_context(|| format!("could not read {:?}", path))?;
+        let matches = query.find_byte_matches(&input);
+        if matches.is_empty() {
+            return Ok(None);
+        }
+        let replacement_text = query.replacement_literal().to_string();
+        let replacements = matches
+            .iter()
+            .map(|&(start, end)| {
+                let (line, column) = line_and_column(&input, start);
+                Replacement {
+                    line,
+                    column,
+                    before: String::from_utf8_lossy(&input[start..end]).into_owned(),
+                    after: replacement_text.clone(),
+                }
+            })
+            .collect();
+        let new_contents = apply_byte_matches(&input, &matches, replacement_text.as_bytes());
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            original_text: None,
+            new_text: None,
+            new_contents: Contents::Bytes(new_contents),
+            replacements,
+        }))
+    }
+
+    fn new_encoded(path: &Path, query: &Query, label: &str) -> Result<Option<Self>> {
+        let raw = fs::read(path).with_context(|| format!("could not read {:?}", path))?;
+        let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+            .with_context(|| format!("unknown encoding: {}", label))?;
+        let (bom, without_bom) = match encoding_rs::Encoding::for_bom(&raw) {
+            Some((_, bom_len)) => raw.split_at(bom_len),
+            None => (&raw[..0], &raw[..]),
+        };
+        let (decoded, _, had_errors) = encoding.decode(without_bom);
+        if had_errors {
+            // Could not decode cleanly: skip, same as an invalid UTF-8 file.
+            return Ok(None);
+        }
+        let (new_text, replacements) = patch_lines(&decoded, query);
+        if replacements.is_empty() {
+            return Ok(None);
+        }
+        // `Encoding::encode` outputs UTF-8 for the UTF-16 encodings, so encode those by hand.
+        let encoded: Vec<u8> = if encoding == encoding_rs::UTF_16LE {
+            new_text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+        } else if encoding == encoding_rs::UTF_16BE {
+            new_text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+        } else {
+            encoding.encode(&new_text).0.into_owned()
+        };
+        let mut new_contents = bom.to_vec();
+        new_contents.extend_from_slice(&encoded);
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            original_text: Some(decoded.into_owned()),
+            new_text: Some(new_text),
+            new_contents: Contents::Bytes(new_contents),
+            replacements,
+        }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replacements
+    }
+
+    pub fn num_replacements(&self) -> usize {
+        self.replacements.len()
+    }
+
+    /// The before/after text of this file, for producing a line-oriented diff.
+    ///
+    /// Returns `None` for files patched in binary mode, since there is no text
+    /// representation to diff against.
+    pub fn diff_text(&self) -> Option<(&str, &str)> {
+        let after = match &self.new_contents {
+            Contents::Text(text) => text.as_str(),
+            Contents::Bytes(_) => self.new_text.as_deref()?,
+        };
+        self.original_text.as_deref().map(|before| (before, after))
+    }
+
+    pub fn write(&self) -> Result<()> {
+        match &self.new_contents {
+            Contents::Text(text) => fs::write(&self.path, text),
+            Contents::Bytes(bytes) => fs::write(&self.path, bytes),
+        }
+        .with_context(|| format!("could not write to {:?}", self.path))
+    }
+}
+
+/// Apply `query` line by line, so each replacement can be reported with its line number.
+fn patch_lines(input: &str, query: &Query) -> (String, Vec<Replacement>) {
+    let mut replacements = Vec::new();
+    let mut out = String::with_capacity(input.len());
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let patched = query.replace(line);
+        if patched != line {
+            let column = common_prefix_len(line, &patched) + 1;
+            replacements.push(Replacement {
+                line: i + 1,
+                column,
+                before: line.trim_end_matches('\n').to_string(),
+                after: patched.trim_end_matches('\n').to_string(),
+            });
+        }
+        out.push_str(&patched);
+    }
+    (out, replacements)
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count()
+}
+
+/// Rebuild `input` with every `(start, end)` byte range in `matches` replaced by `with`,
+/// copying the unmatched spans verbatim.
+fn apply_byte_matches(input: &[u8], matches: &[(usize, usize)], with: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut cursor = 0;
+    for &(start, end) in matches {
+        out.extend_from_slice(&input[cursor..start]);
+        out.extend_from_slice(with);
+        cursor = end;
+    }
+    out.extend_from_slice(&input[cursor..]);
+    out
+}
+
+/// 1-indexed line and column of the byte at `pos`, counting newlines seen so far.
+fn line_and_column(input: &[u8], pos: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut last_newline = None;
+    for (i, &byte) in input[..pos].iter().enumerate() {
+        if byte == b'\n' {
+            line += 1;
+            last_newline = Some(i);
+        }
+    }
+    let column = match last_newline {
+        Some(i) => pos - i,
+        None => pos + 1,
+    };
+    (line, column)
+}
+
+
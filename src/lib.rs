@@ -0,0 +1,16 @@
+//! ruplacer: find and replace text in source trees.
+
+mod directory_patcher;
+mod file_patcher;
+mod output;
+mod query;
+mod settings;
+mod stats;
+
+pub use directory_patcher::DirectoryPatcher;
+pub use file_patcher::{FilePatcher, Replacement};
+pub use output::{FileResult, OutputFormat};
+pub use query::Query;
+pub use settings::Settings;
+pub use stats::Stats;
+
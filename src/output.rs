@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use crate::file_patcher::Replacement;
+
+/// How `DirectoryPatcher` should report the replacements it performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Print nothing and keep no per-file records; the caller reports from `Stats`
+    /// (the historical behavior).
+    #[default]
+    Text,
+    /// Print a unified diff per modified file, suitable for `patch` or code review tooling.
+    UnifiedDiff,
+    /// Print a single JSON array holding one record per modified file.
+    Json,
+}
+
+/// All the replacements performed in a single file, kept around so a unified diff or a
+/// JSON record can be produced from the same pass that patched the file.
+///
+/// `before_text`/`after_text` are only set for files that have a text representation
+/// (i.e. not files patched with `Settings::binary`), since a unified diff only makes sense
+/// for line-oriented content.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileResult {
+    pub path: PathBuf,
+    pub replacements: Vec<Replacement>,
+    #[serde(skip)]
+    pub before_text: Option<String>,
+    #[serde(skip)]
+    pub after_text: Option<String>,
+}
+
+/// Print a real unified diff, with hunk headers and surrounding context, so the output
+/// can be piped straight into `patch` or a code review tool.
+pub fn print_unified_diff(result: &FileResult) {
+    let display_path = result.path.display().to_string();
+    let (before, after) = match (&result.before_text, &result.after_text) {
+        (Some(before), Some(after)) => (before, after),
+        _ => {
+            println!("Binary files {} differ", display_path);
+            return;
+        }
+    };
+    let diff = similar::TextDiff::from_lines(before, after);
+    print!(
+        "{}",
+        diff.unified_diff()
+            .context_radius(3)
+            .header(&display_path, &display_path)
+    );
+}
+
+pub fn print_json(results: &[FileResult]) -> serde_json::Result<()> {
+    println!("{}", serde_json::to_string(results)?);
+    Ok(())
+}
+
@@ -0,0 +1,79 @@
+use std::fmt;
+
+/// Describes the replacement to perform: either a plain substring or a regex.
+#[derive(Debug, Clone)]
+pub enum Query {
+    Substring(String, String),
+    Regex(regex::Regex, String),
+}
+
+impl Query {
+    pub fn substring(old: &str, new: &str) -> Self {
+        Query::Substring(old.to_string(), new.to_string())
+    }
+
+    pub fn from_regex(regex: regex::Regex, new: &str) -> Self {
+        Query::Regex(regex, new.to_string())
+    }
+
+    /// Apply the query to a line of text.
+    pub fn replace(&self, input: &str) -> String {
+        match self {
+            Query::Substring(old, new) => input.replace(old.as_str(), new.as_str()),
+            Query::Regex(regex, new) => regex.replace_all(input, new.as_str()).into_owned(),
+        }
+    }
+
+    /// Find every match of this query in raw bytes, without requiring them to be valid
+    /// UTF-8. Returns the `(start, end)` byte range of each match, in order.
+    pub fn find_byte_matches(&self, input: &[u8]) -> Vec<(usize, usize)> {
+        match self {
+            Query::Substring(old, _) => find_byte_substring_matches(input, old.as_bytes()),
+            Query::Regex(regex, _) => {
+                // The pattern was already validated when the text `Regex` was built.
+                let bytes_regex = regex::bytes::Regex::new(regex.as_str())
+                    .expect("regex pattern should still be valid as a bytes::Regex");
+                bytes_regex
+                    .find_iter(input)
+                    .map(|m| (m.start(), m.end()))
+                    .collect()
+            }
+        }
+    }
+
+    /// The literal replacement text for this query (ignores regex capture references).
+    pub fn replacement_literal(&self) -> &str {
+        match self {
+            Query::Substring(_, new) | Query::Regex(_, new) => new,
+        }
+    }
+}
+
+fn find_byte_substring_matches(input: &[u8], needle: &[u8]) -> Vec<(usize, usize)> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    let mut offset = 0;
+    while let Some(pos) = find_subslice(&input[offset..], needle) {
+        let start = offset + pos;
+        let end = start + needle.len();
+        matches.push((start, end));
+        offset = end;
+    }
+    matches
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Substring(old, new) => write!(f, "'{}' -> '{}'", old, new),
+            Query::Regex(regex, new) => write!(f, "'{}' -> '{}'", regex, new),
+        }
+    }
+}
+
@@ -0,0 +1,35 @@
+use crate::output::OutputFormat;
+
+/// Controls how `DirectoryPatcher` walks a tree and patches the files it finds.
+#[derive(Debug, Default)]
+pub struct Settings {
+    /// Do everything except actually writing the files.
+    pub dry_run: bool,
+    /// How to report the replacements that were performed.
+    pub output_format: OutputFormat,
+    /// Also look into hidden files and directories.
+    pub hidden: bool,
+    /// Also look into files and directories usually excluded by `.gitignore`.
+    pub ignored: bool,
+    /// Only patch files whose name matches one of these extensions or glob patterns.
+    pub selected_file_types: Vec<String>,
+    /// Skip files whose name matches one of these extensions or glob patterns.
+    pub ignored_file_types: Vec<String>,
+    /// Restrict the walk to these path patterns (e.g. `"src/**/*.rs"`) instead of the
+    /// whole tree rooted at the run path.
+    pub include_patterns: Vec<String>,
+    /// Extra per-directory ignore file names to honor, in addition to `.gitignore`
+    /// (e.g. `".ruplacerignore"`).
+    pub custom_ignore_filenames: Vec<String>,
+    /// Read the list of files to patch from this newline-delimited file instead of
+    /// walking the directory tree.
+    pub files_from: Option<std::path::PathBuf>,
+    /// Patch files even when they are not valid UTF-8, operating on raw bytes instead.
+    ///
+    /// Mutually exclusive with `encoding`: when both are set, `binary` wins.
+    pub binary: bool,
+    /// Decode files using this encoding (as understood by `encoding_rs`, e.g. `"latin1"`
+    /// or `"utf-16le"`) instead of assuming UTF-8.
+    pub encoding: Option<String>,
+}
+
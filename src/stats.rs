@@ -0,0 +1,35 @@
+use crate::output::FileResult;
+
+/// Summary of a `DirectoryPatcher::run` call.
+#[derive(Debug, Default)]
+pub struct Stats {
+    matching_files: usize,
+    total_replacements: usize,
+    file_results: Vec<FileResult>,
+}
+
+impl Stats {
+    pub fn matching_files(&self) -> usize {
+        self.matching_files
+    }
+
+    pub fn total_replacements(&self) -> usize {
+        self.total_replacements
+    }
+
+    /// Per-file replacement records, populated when `Settings::output_format` is not
+    /// `OutputFormat::Text`.
+    pub fn file_results(&self) -> &[FileResult] {
+        &self.file_results
+    }
+
+    pub(crate) fn on_file_patched(&mut self, num_replacements: usize) {
+        self.matching_files += 1;
+        self.total_replacements += num_replacements;
+    }
+
+    pub(crate) fn add_file_result(&mut self, file_result: FileResult) {
+        self.file_results.push(file_result);
+    }
+}
+
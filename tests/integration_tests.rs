@@ -7,13 +7,13 @@ use tempfile::TempDir;
 
 use ruplacer::Query;
 use ruplacer::Settings;
-use ruplacer::{DirectoryPatcher, Stats};
+use ruplacer::{DirectoryPatcher, OutputFormat, Stats};
 
 fn setup_test(tmp_dir: &TempDir) -> PathBuf {
     let tmp_path = tmp_dir.path();
     #[cfg(not(target_os = "windows"))]
     let status = Command::new("cp")
-        .args(&["-R", "tests/data", &tmp_path.to_string_lossy()])
+        .args(["-R", "tests/data", &tmp_path.to_string_lossy()])
         .status()
         .expect("Failed to execute process");
     #[cfg(target_os = "windows")]
@@ -32,14 +32,14 @@ fn setup_test(tmp_dir: &TempDir) -> PathBuf {
 
 fn assert_replaced(path: &Path) {
     let contents =
-        fs::read_to_string(&path).unwrap_or_else(|_| panic!("Could not read from {:?}", path));
+        fs::read_to_string(path).unwrap_or_else(|_| panic!("Could not read from {:?}", path));
     assert!(contents.contains("new"));
     assert!(!contents.contains("old"));
 }
 
 fn assert_not_replaced(path: &Path) {
     let contents =
-        fs::read_to_string(&path).unwrap_or_else(|_| panic!("Could not read from {:?}", path));
+        fs::read_to_string(path).unwrap_or_else(|_| panic!("Could not read from {:?}", path));
     assert!(!contents.contains("new"));
     assert!(contents.contains("old"));
 }
@@ -154,6 +154,50 @@ fn test_skip_non_utf8_files() {
     run_ruplacer(&data_path, settings).unwrap();
 }
 
+#[test]
+fn test_binary_replacements_have_real_metadata() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+    let bin_path = data_path.join("foo.bin");
+    fs::write(&bin_path, b"line one\nthis is old\xff\nold again\n").unwrap();
+
+    let settings = Settings {
+        binary: true,
+        output_format: OutputFormat::Json,
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    let result = stats
+        .file_results()
+        .iter()
+        .find(|r| r.path == bin_path)
+        .expect("binary file should have a result");
+    assert_eq!(result.replacements.len(), 2);
+    assert_eq!(result.replacements[0].line, 2);
+    assert_eq!(result.replacements[0].before, "old");
+    assert_eq!(result.replacements[0].after, "new");
+    assert_eq!(result.replacements[1].line, 3);
+    assert_eq!(result.replacements[1].before, "old");
+}
+
+#[test]
+fn test_encoding_aware_patching() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+    let latin1_path = data_path.join("foo.latin1.txt");
+    fs::write(&latin1_path, b"caf\xe9 is old\n").unwrap();
+
+    let settings = Settings {
+        encoding: Some("latin1".to_string()),
+        ..Default::default()
+    };
+    run_ruplacer(&data_path, settings).unwrap();
+
+    let patched = fs::read(&latin1_path).unwrap();
+    assert_eq!(patched, b"caf\xe9 is new\n");
+}
+
 fn add_python_file(data_path: &Path) -> PathBuf {
     let py_path = data_path.join("foo.py");
     fs::write(&py_path, "a = 'this is old'\n").unwrap();
@@ -260,6 +304,59 @@ fn test_ignore_file_types_by_glob_pattern_2() {
     assert_not_replaced(&py_path);
 }
 
+#[test]
+fn test_include_patterns_restricts_to_matching_subtree() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+
+    let settings = Settings {
+        include_patterns: vec!["a_dir/**".to_string()],
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    assert_eq!(stats.matching_files(), 1);
+    assert_replaced(&data_path.join("a_dir/sub/foo.txt"));
+    assert_not_replaced(&data_path.join("top.txt"));
+}
+
+#[test]
+fn test_include_pattern_with_bare_star_does_not_recurse() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+    let direct_path = data_path.join("a_dir/direct.txt");
+    fs::write(&direct_path, "this is old\n").unwrap();
+    let nested_path = data_path.join("a_dir/sub/foo.txt");
+
+    let settings = Settings {
+        include_patterns: vec!["a_dir/*.txt".to_string()],
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    assert_eq!(stats.matching_files(), 1);
+    assert_replaced(&direct_path);
+    assert_not_replaced(&nested_path);
+}
+
+#[test]
+fn test_select_file_types_only_visits_matching_files_lazily() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+    add_python_file(&data_path);
+
+    // A selected type that matches nothing should not touch any other file, and
+    // should not error out even though no glob is pre-expanded against the tree.
+    let settings = Settings {
+        selected_file_types: vec!["md".to_string()],
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    assert_eq!(stats.matching_files(), 0);
+    assert_not_replaced(&data_path.join("top.txt"));
+}
+
 #[test]
 fn test_ignore_file_types_by_incorrect_glob_pattern() {
     let tmp_dir = temp_dir();
@@ -271,3 +368,86 @@ fn test_ignore_file_types_by_incorrect_glob_pattern() {
     let err = run_ruplacer(&data_path, settings).unwrap_err();
     assert!(err.to_string().contains("unrecognized file type"));
 }
+
+#[test]
+fn test_json_output_carries_replacements() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+
+    let settings = Settings {
+        output_format: OutputFormat::Json,
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    let top_txt_path = data_path.join("top.txt");
+    let result = stats
+        .file_results()
+        .iter()
+        .find(|r| r.path == top_txt_path)
+        .expect("top.txt should have a file result");
+    assert_eq!(result.replacements.len(), 1);
+    assert_eq!(result.replacements[0].before, "this is old");
+    assert_eq!(result.replacements[0].after, "this is new");
+}
+
+#[test]
+fn test_unified_diff_output_has_context_and_hunk_header() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+
+    let settings = Settings {
+        output_format: OutputFormat::UnifiedDiff,
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    let top_txt_path = data_path.join("top.txt");
+    let result = stats
+        .file_results()
+        .iter()
+        .find(|r| r.path == top_txt_path)
+        .expect("top.txt should have a file result");
+    assert_eq!(
+        result.before_text.as_deref(),
+        Some("this is old\n"),
+        "before_text should hold the full original contents, not just the matched line"
+    );
+    assert_eq!(result.after_text.as_deref(), Some("this is new\n"));
+}
+
+#[test]
+fn test_custom_ignore_filenames() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+    let py_path = add_python_file(&data_path);
+    fs::write(data_path.join(".ruplacerignore"), "foo.py\n").unwrap();
+
+    let settings = Settings {
+        custom_ignore_filenames: vec![".ruplacerignore".to_string()],
+        ..Default::default()
+    };
+    run_ruplacer(&data_path, settings).unwrap();
+
+    assert_not_replaced(&py_path);
+}
+
+#[test]
+fn test_files_from() {
+    let tmp_dir = temp_dir();
+    let data_path = setup_test(&tmp_dir);
+    let foo_path = data_path.join("a_dir/sub/foo.txt");
+
+    let files_from_path = data_path.join("files.txt");
+    fs::write(&files_from_path, "a_dir/sub/foo.txt\n").unwrap();
+
+    let settings = Settings {
+        files_from: Some(files_from_path),
+        ..Default::default()
+    };
+    let stats = run_ruplacer(&data_path, settings).unwrap();
+
+    assert_eq!(stats.matching_files(), 1);
+    assert_replaced(&foo_path);
+    assert_not_replaced(&data_path.join("top.txt"));
+}